@@ -11,6 +11,10 @@ use std::borrow::{BorrowMut};
 use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
 use chrono::{DateTime, Local, TimeZone};
 
+use digest::Digest as DigestTrait;
+use sha1::Sha1;
+use sha3::Sha3_256;
+
 pub type Result<T> = std::result::Result<T, ErrorKind>;
 
 #[derive(Debug)]
@@ -18,7 +22,10 @@ pub enum ErrorKind {
     /// An bad discriminant was found when trying to deserialise an enum. Value of the discriminant
     BadDiscriminant(u128),
 
-    ///Raised when a cell has been discarded (due to bad discriminant). Value of the bad discriminant
+    ///Raised when a cell has been discarded, either due to a bad discriminant, or (in
+    ///`DigestingReader::read_relay_cell`) a digest mismatch on a relay cell whose non-zero
+    ///`recognized` field indicates it wasn't meant for this hop. Value of the bad discriminant, or
+    ///of the cell's `recognized` field in the relay-digest case
     DiscardedCell(u128),
 
     /// A predicted digest does not match the actual. Predicted and actual digests
@@ -30,6 +37,18 @@ pub enum ErrorKind {
     /// There are fewer bytes of padding than there should be to make up the 509 bytes. Number of bytes expected, number of bytes read
     NotEnoughPadding(usize, usize),
 
+    /// Deserialising the next value would exceed the remaining `DeserialiseLimit` budget. Amount requested, amount remaining
+    SizeLimitExceeded(u32, u32),
+
+    /// A CIDR string failed to parse into an address and prefix length (missing `/`, malformed address, or non-numeric prefix)
+    InvalidNetFormat,
+
+    /// A prefix length exceeds the number of bits in the address family (32 for IPv4, 128 for IPv6). The value given
+    InvalidPrefixLength(u8),
+
+    /// A `CompressedUint`'s declared byte length exceeds the width of the target integer type. Declared length, target width
+    CompressedUintTooWide(u8, u8),
+
     /// A call to a bincode function failed
     BincodeError(bincode::ErrorKind),
 
@@ -54,15 +73,142 @@ lazy_static! {
     static ref BINCODE_OPTIONS: WithOtherEndian<WithOtherIntEncoding<DefaultOptions, FixintEncoding>, BigEndian> = bincode::config::DefaultOptions::new().with_fixint_encoding().with_big_endian();
 }
 
+///Tracks the remaining byte and (optionally) element budget while deserialising untrusted data,
+///so a hostile length field can't force an unbounded allocation before a single byte has been
+///validated. Ported from the idea behind bincode's `config::limit`.
+#[derive(Debug, Clone, Copy)]
+pub struct DeserialiseLimit {
+    remaining_bytes: Option<u32>,
+    remaining_elements: Option<u32>,
+}
+
+impl DeserialiseLimit {
+    ///The size, in bytes, of a single fixed-length Tor cell. Used as the byte budget by `Default`
+    ///when a caller doesn't supply an explicit limit.
+    pub const FIXED_CELL_LIMIT: u32 = 509;
+
+    ///A limit with no byte or element budget; deserialisation may consume as much as the stream provides
+    pub fn unbounded() -> Self {
+        Self { remaining_bytes: None, remaining_elements: None }
+    }
+
+    ///Sets the remaining-byte budget, replacing whatever was there before
+    pub fn with_byte_limit(mut self, limit: u32) -> Self {
+        self.remaining_bytes = Some(limit);
+        self
+    }
+
+    ///Sets the remaining-element budget, replacing whatever was there before
+    pub fn with_element_limit(mut self, limit: u32) -> Self {
+        self.remaining_elements = Some(limit);
+        self
+    }
+
+    ///Decrements the byte budget by `amount`, failing with `SizeLimitExceeded` rather than going negative
+    fn consume_bytes(&mut self, amount: u32) -> Result<()> {
+        if let Some(remaining) = self.remaining_bytes {
+            if amount > remaining {
+                return Err(ErrorKind::SizeLimitExceeded(amount, remaining));
+            }
+            self.remaining_bytes = Some(remaining - amount);
+        }
+        Ok(())
+    }
+
+    ///Decrements the element budget by one, failing with `SizeLimitExceeded` rather than going negative
+    fn consume_element(&mut self) -> Result<()> {
+        if let Some(remaining) = self.remaining_elements {
+            if remaining == 0 {
+                return Err(ErrorKind::SizeLimitExceeded(1, 0));
+            }
+            self.remaining_elements = Some(remaining - 1);
+        }
+        Ok(())
+    }
+
+    ///The number of bytes still allowed, if the budget is bounded
+    fn remaining_bytes(&self) -> Option<u32> {
+        self.remaining_bytes
+    }
+}
+
+impl Default for DeserialiseLimit {
+    ///Defaults to the 509-byte budget of a single fixed-length Tor cell, with no element budget
+    fn default() -> Self {
+        Self { remaining_bytes: Some(Self::FIXED_CELL_LIMIT), remaining_elements: None }
+    }
+}
+
+///Caps a declared element count to what `limit`'s remaining byte budget could actually hold,
+///given each element's minimum possible size, so a hostile/oversized declared length doesn't
+///force an upfront allocation before a single element has been validated
+fn safe_capacity(limit: &DeserialiseLimit, min_element_size: u32, declared: u32) -> u32 {
+    limit.remaining_bytes()
+        .map(|remaining| (remaining / min_element_size.max(1)).min(declared))
+        .unwrap_or(declared)
+}
+
+///The negotiated link/circuit protocol version, following the numbering in the Tor spec (e.g. link
+///protocol 4 introduces variable-length cell framing, and CIRCID widens from 2 to 4 bytes at version 4)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ProtocolVersion(pub u16);
+
+impl ProtocolVersion {
+    ///The newest link protocol version this crate understands
+    pub const LATEST: ProtocolVersion = ProtocolVersion(5);
+
+    ///The link protocol version at which `CIRCID` widens from 2 to 4 bytes
+    pub const CIRCID_WIDENS_AT: ProtocolVersion = ProtocolVersion(4);
+}
+
+impl Default for ProtocolVersion {
+    fn default() -> Self {
+        Self::LATEST
+    }
+}
+
+///Carries the negotiated `ProtocolVersion`, plus any per-call framing hints that can't be read
+///from the stream itself (e.g. a VersionsVector's element count), through every
+///`bin_serialise_into`/`bin_deserialise_from` call. Replaces the old untyped `Option<u32>` context
+///so impls have a single place to branch on version rather than smuggling version-specific data
+///through an ad-hoc parameter
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SerCtx {
+    pub version: ProtocolVersion,
+    pub versions_vector_length: Option<u32>,
+}
+
+impl SerCtx {
+    ///A context carrying the given protocol version and no framing hints
+    pub fn new(version: ProtocolVersion) -> Self {
+        Self { version, versions_vector_length: None }
+    }
+
+    ///Attaches the VersionsVector element count, needed since that payload doesn't self-describe its length
+    pub fn with_versions_vector_length(mut self, length: u32) -> Self {
+        self.versions_vector_length = Some(length);
+        self
+    }
+}
+
 ///TorSerde trait exposes functions that serialise and deserialise data in accordance with the Tor specification
 pub trait TorSerde {
 
     ///Return the length of the data when serialised, in bytes. Used when the length of the payload is required
-    fn bin_serialise_into<W: Write>(&self, stream: W) -> Result<u32>;
+    fn bin_serialise_into<W: Write>(&self, stream: W, ctx: &SerCtx) -> Result<u32>;
 
-    fn bin_deserialise_from<R: Read>(stream: R) -> Result<Self> where Self: Sized;
+    ///Deserialises from `stream`, consulting `ctx` for the negotiated protocol version and any
+    ///per-call framing hints (e.g. the VersionsVector element count), and decrementing `limit` as
+    ///bytes/elements are consumed so a hostile length field can't force an unbounded allocation
+    fn bin_deserialise_from<R: Read>(stream: R, ctx: &SerCtx, limit: &mut DeserialiseLimit) -> Result<Self> where Self: Sized;
 
     fn serialised_length(&self) -> u32;
+
+    ///The smallest number of bytes any instance of this type can serialise to. Used to bound
+    ///`Vec` pre-allocation against a remaining byte budget without trusting a declared element count
+    fn min_serialised_length() -> u32 where Self: Sized {
+        1
+    }
 }
 
 ///A wrapper used to handle the Versions cell payload, which (unlike pretty much all other variable length cell payloads) does not contain its own length
@@ -98,12 +244,13 @@ impl<T: TorSerde, const N: usize> From<NLengthVector<T, N>> for Vec<T> {
 }
 
 impl TorSerde for u8 {
-    fn bin_serialise_into<W: Write>(&self, stream: W) -> Result<u32> {
+    fn bin_serialise_into<W: Write>(&self, stream: W, _ctx: &SerCtx) -> Result<u32> {
         BINCODE_OPTIONS.serialize_into(stream, &self)?;
         Ok(self.serialised_length())
     }
 
-    fn bin_deserialise_from<R: Read>(stream: R) -> Result<Self> {
+    fn bin_deserialise_from<R: Read>(stream: R, _ctx: &SerCtx, limit: &mut DeserialiseLimit) -> Result<Self> {
+        limit.consume_bytes(1)?;
         let res: Self = BINCODE_OPTIONS.deserialize_from(stream)?;
         Ok(res)
     }
@@ -112,15 +259,20 @@ impl TorSerde for u8 {
         1
     }
 
+    fn min_serialised_length() -> u32 {
+        1
+    }
+
 }
 
 impl TorSerde for u16 {
-    fn bin_serialise_into<W: Write>(&self, stream: W) -> Result<u32> {
+    fn bin_serialise_into<W: Write>(&self, stream: W, _ctx: &SerCtx) -> Result<u32> {
         BINCODE_OPTIONS.serialize_into(stream, &self)?;
         Ok(self.serialised_length())
     }
 
-    fn bin_deserialise_from<R: Read>(stream: R) -> Result<Self> {
+    fn bin_deserialise_from<R: Read>(stream: R, _ctx: &SerCtx, limit: &mut DeserialiseLimit) -> Result<Self> {
+        limit.consume_bytes(2)?;
         let res: Self = BINCODE_OPTIONS.deserialize_from(stream)?;
         Ok(res)
     }
@@ -128,15 +280,20 @@ impl TorSerde for u16 {
     fn serialised_length(&self) -> u32 {
         2
     }
+
+    fn min_serialised_length() -> u32 {
+        2
+    }
 }
 
 impl TorSerde for u32 {
-    fn bin_serialise_into<W: Write>(&self, stream: W) -> Result<u32> {
+    fn bin_serialise_into<W: Write>(&self, stream: W, _ctx: &SerCtx) -> Result<u32> {
         BINCODE_OPTIONS.serialize_into(stream, &self)?;
         Ok(self.serialised_length())
     }
 
-    fn bin_deserialise_from<R: Read>(stream: R) -> Result<Self> {
+    fn bin_deserialise_from<R: Read>(stream: R, _ctx: &SerCtx, limit: &mut DeserialiseLimit) -> Result<Self> {
+        limit.consume_bytes(4)?;
         let res: Self = BINCODE_OPTIONS.deserialize_from(stream)?;
         Ok(res)
     }
@@ -144,15 +301,20 @@ impl TorSerde for u32 {
     fn serialised_length(&self) -> u32 {
         4
     }
+
+    fn min_serialised_length() -> u32 {
+        4
+    }
 }
 
 impl TorSerde for u64 {
-    fn bin_serialise_into<W: Write>(&self, stream: W) -> Result<u32> {
+    fn bin_serialise_into<W: Write>(&self, stream: W, _ctx: &SerCtx) -> Result<u32> {
         BINCODE_OPTIONS.serialize_into(stream, &self)?;
         Ok(self.serialised_length())
     }
 
-    fn bin_deserialise_from<R: Read>(stream: R) -> Result<Self> {
+    fn bin_deserialise_from<R: Read>(stream: R, _ctx: &SerCtx, limit: &mut DeserialiseLimit) -> Result<Self> {
+        limit.consume_bytes(8)?;
         let res: Self = BINCODE_OPTIONS.deserialize_from(stream)?;
         Ok(res)
     }
@@ -160,15 +322,20 @@ impl TorSerde for u64 {
     fn serialised_length(&self) -> u32 {
         8
     }
+
+    fn min_serialised_length() -> u32 {
+        8
+    }
 }
 
 impl TorSerde for u128 {
-    fn bin_serialise_into<W: Write>(&self, stream: W) -> Result<u32> {
+    fn bin_serialise_into<W: Write>(&self, stream: W, _ctx: &SerCtx) -> Result<u32> {
         BINCODE_OPTIONS.serialize_into(stream, &self)?;
         Ok(self.serialised_length())
     }
 
-    fn bin_deserialise_from<R: Read>(stream: R) -> Result<Self> {
+    fn bin_deserialise_from<R: Read>(stream: R, _ctx: &SerCtx, limit: &mut DeserialiseLimit) -> Result<Self> {
+        limit.consume_bytes(16)?;
         let res: Self = BINCODE_OPTIONS.deserialize_from(stream)?;
         Ok(res)
     }
@@ -176,38 +343,203 @@ impl TorSerde for u128 {
     fn serialised_length(&self) -> u32 {
         16
     }
+
+    fn min_serialised_length() -> u32 {
+        16
+    }
+}
+
+///A circuit identifier. Serialised as 2 bytes before `ProtocolVersion::CIRCID_WIDENS_AT`, and as 4
+///bytes from that version onward, per the Tor spec's CIRCID width change - the concrete example of
+///branching on `SerCtx::version` that motivated threading it through every impl in the first place
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct CircId(pub u32);
+
+impl TorSerde for CircId {
+    fn bin_serialise_into<W: Write>(&self, stream: W, ctx: &SerCtx) -> Result<u32> {
+        if ctx.version < ProtocolVersion::CIRCID_WIDENS_AT {
+            (self.0 as u16).bin_serialise_into(stream, ctx)
+        } else {
+            self.0.bin_serialise_into(stream, ctx)
+        }
+    }
+
+    fn bin_deserialise_from<R: Read>(stream: R, ctx: &SerCtx, limit: &mut DeserialiseLimit) -> Result<Self> {
+        if ctx.version < ProtocolVersion::CIRCID_WIDENS_AT {
+            Ok(Self(u16::bin_deserialise_from(stream, ctx, limit)? as u32))
+        } else {
+            Ok(Self(u32::bin_deserialise_from(stream, ctx, limit)?))
+        }
+    }
+
+    ///`TorSerde::serialised_length` isn't passed a `SerCtx`, so the exact pre/post-v4 width can't
+    ///be reported here; this is the worst-case (post-widening) 4-byte length
+    fn serialised_length(&self) -> u32 {
+        4
+    }
+
+    fn min_serialised_length() -> u32 {
+        2
+    }
+}
+
+///A 256-bit unsigned integer, stored as 32 big-endian bytes. For descriptor fields too wide for `u128`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct U256(pub [u8; 32]);
+
+impl TorSerde for U256 {
+    fn bin_serialise_into<W: Write>(&self, stream: W, ctx: &SerCtx) -> Result<u32> {
+        self.0.bin_serialise_into(stream, ctx)
+    }
+
+    fn bin_deserialise_from<R: Read>(stream: R, ctx: &SerCtx, limit: &mut DeserialiseLimit) -> Result<Self> {
+        Ok(Self(<[u8; 32]>::bin_deserialise_from(stream, ctx, limit)?))
+    }
+
+    fn serialised_length(&self) -> u32 {
+        32
+    }
+
+    fn min_serialised_length() -> u32 {
+        32
+    }
+}
+
+///A 256-bit signed integer in two's-complement form, stored as 32 big-endian bytes. For descriptor fields too wide for `u128`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct I256(pub [u8; 32]);
+
+impl TorSerde for I256 {
+    fn bin_serialise_into<W: Write>(&self, stream: W, ctx: &SerCtx) -> Result<u32> {
+        self.0.bin_serialise_into(stream, ctx)
+    }
+
+    fn bin_deserialise_from<R: Read>(stream: R, ctx: &SerCtx, limit: &mut DeserialiseLimit) -> Result<Self> {
+        Ok(Self(<[u8; 32]>::bin_deserialise_from(stream, ctx, limit)?))
+    }
+
+    fn serialised_length(&self) -> u32 {
+        32
+    }
+
+    fn min_serialised_length() -> u32 {
+        32
+    }
+}
+
+///Marker trait for integer types whose serialised width is the same in every `SerCtx` - required
+///by `CompressedUint<T>`, which pads/unpads against `T::min_serialised_length()` and so needs that
+///bound to equal `T`'s actual wire width unconditionally. A version-dependent width like `CircId`'s
+///would silently break the round trip for contexts where the real width differs from the minimum
+pub trait FixedWidthInt: TorSerde {}
+
+impl FixedWidthInt for u8 {}
+impl FixedWidthInt for u16 {}
+impl FixedWidthInt for u32 {}
+impl FixedWidthInt for u64 {}
+impl FixedWidthInt for u128 {}
+impl FixedWidthInt for U256 {}
+impl FixedWidthInt for I256 {}
+
+///Self-delimiting big-endian integer encoding: a single length byte giving the number of
+///significant bytes, followed by the minimal big-endian representation with leading zero bytes
+///stripped. Saves space on descriptor and hidden-service-parameter fields whose magnitude is
+///usually far smaller than their declared width. Borrows the trimmed-big-endian scheme from the
+///ethnum serde module
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CompressedUint<T>(pub T);
+
+impl<T: FixedWidthInt> CompressedUint<T> {
+    ///Serialises the wrapped value at its full fixed width, then strips the leading zero bytes
+    fn trimmed_bytes(&self, ctx: &SerCtx) -> Result<Vec<u8>> {
+        let mut full = Vec::with_capacity(T::min_serialised_length() as usize);
+        self.0.bin_serialise_into(&mut full, ctx)?;
+
+        let first_nonzero = full.iter().position(|&b| b != 0).unwrap_or(full.len());
+
+        Ok(full.split_off(first_nonzero))
+    }
+}
+
+impl<T: FixedWidthInt> TorSerde for CompressedUint<T> {
+    fn bin_serialise_into<W: Write>(&self, mut stream: W, ctx: &SerCtx) -> Result<u32> {
+        let trimmed = self.trimmed_bytes(ctx)?;
+
+        let mut total = (trimmed.len() as u8).bin_serialise_into(stream.borrow_mut(), ctx)?;
+
+        for byte in trimmed.iter() {
+            total += byte.bin_serialise_into(stream.borrow_mut(), ctx)?;
+        }
+
+        Ok(total)
+    }
+
+    fn bin_deserialise_from<R: Read>(mut stream: R, ctx: &SerCtx, limit: &mut DeserialiseLimit) -> Result<Self> {
+        let declared_len = u8::bin_deserialise_from(stream.borrow_mut(), ctx, limit)? as usize;
+        let width = T::min_serialised_length() as usize;
+
+        if declared_len > width {
+            return Err(ErrorKind::CompressedUintTooWide(declared_len as u8, width as u8));
+        }
+
+        let mut padded = vec![0u8; width];
+
+        for byte in padded[width - declared_len..].iter_mut() {
+            *byte = u8::bin_deserialise_from(stream.borrow_mut(), ctx, limit)?;
+        }
+
+        //The padded buffer is an in-memory reconstruction, not untrusted stream data, so it's
+        //parsed against a fresh unbounded limit rather than double-consuming the caller's budget
+        let value = T::bin_deserialise_from(padded.as_slice(), ctx, &mut DeserialiseLimit::unbounded())?;
+
+        Ok(Self(value))
+    }
+
+    fn serialised_length(&self) -> u32 {
+        //Infallible: writing a fixed-width integer into a growable `Vec<u8>` cannot fail
+        1 + self.trimmed_bytes(&SerCtx::default()).expect("serialising an integer into a Vec cannot fail").len() as u32
+    }
+
+    fn min_serialised_length() -> u32 {
+        1
+    }
 }
 
 impl<T: TorSerde, const N: usize> TorSerde for NLengthVector<T, N> {
-    fn bin_serialise_into<W: Write>(&self, mut stream: W) -> Result<u32> {
+    fn bin_serialise_into<W: Write>(&self, mut stream: W, ctx: &SerCtx) -> Result<u32> {
         //let mut total = N as u32;
 
         let mut total = match N {
-            1 => (self.0.len() as u8).bin_serialise_into(stream.borrow_mut())?,
-            2 => (self.0.len() as u16).bin_serialise_into(stream.borrow_mut())?,
-            4 => (self.0.len() as u32).bin_serialise_into(stream.borrow_mut())?,
+            1 => (self.0.len() as u8).bin_serialise_into(stream.borrow_mut(), ctx)?,
+            2 => (self.0.len() as u16).bin_serialise_into(stream.borrow_mut(), ctx)?,
+            4 => (self.0.len() as u32).bin_serialise_into(stream.borrow_mut(), ctx)?,
             _ => unreachable!()
         };
 
         for item in self.0.iter() {
-            total += item.bin_serialise_into(stream.borrow_mut())?;
+            total += item.bin_serialise_into(stream.borrow_mut(), ctx)?;
         }
 
         Ok(total)
     }
 
-    fn bin_deserialise_from<R: Read>(mut stream: R) -> Result<Self> {
+    fn bin_deserialise_from<R: Read>(mut stream: R, ctx: &SerCtx, limit: &mut DeserialiseLimit) -> Result<Self> {
         let length = match N {
-            1 => u8::bin_deserialise_from(stream.borrow_mut())? as u32,
-            2 => u16::bin_deserialise_from(stream.borrow_mut())? as u32,
-            4 => u32::bin_deserialise_from(stream.borrow_mut())?,
+            1 => u8::bin_deserialise_from(stream.borrow_mut(), ctx, limit)? as u32,
+            2 => u16::bin_deserialise_from(stream.borrow_mut(), ctx, limit)? as u32,
+            4 => u32::bin_deserialise_from(stream.borrow_mut(), ctx, limit)?,
             _ => unreachable!()
         };
 
-        let mut list = Vec::with_capacity(length as usize);
+        //Never trust `length` for pre-allocation: cap it to what the remaining byte budget could
+        //actually hold, and let the Vec grow incrementally (via push) for the rest
+        let capacity = safe_capacity(limit, T::min_serialised_length(), length);
+
+        let mut list = Vec::with_capacity(capacity as usize);
 
         for _ in 0..length {
-            list.push(T::bin_deserialise_from(stream.borrow_mut())?);
+            limit.consume_element()?;
+            list.push(T::bin_deserialise_from(stream.borrow_mut(), ctx, limit)?);
         }
 
         Ok(Self(list))
@@ -219,22 +551,27 @@ impl<T: TorSerde, const N: usize> TorSerde for NLengthVector<T, N> {
 }
 
 impl TorSerde for VersionsVector {
-    fn bin_serialise_into<W: Write>(&self, mut stream: W) -> Result<u32> {
-        ((self.0.len()*2) as u16).bin_serialise_into(stream.borrow_mut())?;
-
+    fn bin_serialise_into<W: Write>(&self, mut stream: W, ctx: &SerCtx) -> Result<u32> {
         for item in self.0.iter() {
-            item.bin_serialise_into(stream.borrow_mut())?;
+            item.bin_serialise_into(stream.borrow_mut(), ctx)?;
         }
 
         Ok(self.serialised_length())
     }
 
-    fn bin_deserialise_from<R: Read>(mut stream: R) -> Result<Self> {
-        let length = u16::bin_deserialise_from(stream.borrow_mut())? / 2;
-        let mut list = Vec::with_capacity(length as usize);
+    fn bin_deserialise_from<R: Read>(mut stream: R, ctx: &SerCtx, limit: &mut DeserialiseLimit) -> Result<Self> {
+        //Unlike NLengthVector, the element count isn't in the stream at all here - it comes from
+        //the caller via `ctx.versions_vector_length` (e.g. half the enclosing cell's payload length,
+        //since each version is 2 bytes)
+        let count = ctx.versions_vector_length.unwrap_or(0);
 
-        for _ in 0..length {
-            list.push(u16::bin_deserialise_from(stream.borrow_mut())?);
+        let capacity = safe_capacity(limit, u16::min_serialised_length(), count);
+
+        let mut list = Vec::with_capacity(capacity as usize);
+
+        for _ in 0..count {
+            limit.consume_element()?;
+            list.push(u16::bin_deserialise_from(stream.borrow_mut(), ctx, limit)?);
         }
 
         Ok(Self(list))
@@ -246,12 +583,12 @@ impl TorSerde for VersionsVector {
 }
 
 impl TorSerde for DateTime<Local> {
-    fn bin_serialise_into<W: Write>(&self, stream: W) -> Result<u32> {
-        (self.timestamp() as u32).bin_serialise_into(stream)
+    fn bin_serialise_into<W: Write>(&self, stream: W, ctx: &SerCtx) -> Result<u32> {
+        (self.timestamp() as u32).bin_serialise_into(stream, ctx)
     }
 
-    fn bin_deserialise_from<R: Read>(stream: R) -> Result<Self> {
-        let timestamp = u32::bin_deserialise_from(stream)?;
+    fn bin_deserialise_from<R: Read>(stream: R, ctx: &SerCtx, limit: &mut DeserialiseLimit) -> Result<Self> {
+        let timestamp = u32::bin_deserialise_from(stream, ctx, limit)?;
 
         Ok(Local.timestamp(timestamp as i64, 0))
     }
@@ -259,17 +596,21 @@ impl TorSerde for DateTime<Local> {
     fn serialised_length(&self) -> u32 {
         4
     }
+
+    fn min_serialised_length() -> u32 {
+        4
+    }
 }
 
 impl TorSerde for Ipv4Addr {
-    fn bin_serialise_into<W: Write>(&self, stream: W) -> Result<u32> {
+    fn bin_serialise_into<W: Write>(&self, stream: W, ctx: &SerCtx) -> Result<u32> {
         let bytes = u32::from(self.clone());
 
-        bytes.bin_serialise_into(stream)
+        bytes.bin_serialise_into(stream, ctx)
     }
 
-    fn bin_deserialise_from<R: Read>(stream: R) -> Result<Self> {
-        let bytes = u32::bin_deserialise_from(stream)?;
+    fn bin_deserialise_from<R: Read>(stream: R, ctx: &SerCtx, limit: &mut DeserialiseLimit) -> Result<Self> {
+        let bytes = u32::bin_deserialise_from(stream, ctx, limit)?;
 
         Ok(Self::from(bytes))
     }
@@ -277,17 +618,21 @@ impl TorSerde for Ipv4Addr {
     fn serialised_length(&self) -> u32 {
         4
     }
+
+    fn min_serialised_length() -> u32 {
+        4
+    }
 }
 
 impl TorSerde for Ipv6Addr {
-    fn bin_serialise_into<W: Write>(&self, stream: W) -> Result<u32> {
+    fn bin_serialise_into<W: Write>(&self, stream: W, ctx: &SerCtx) -> Result<u32> {
         let bytes = u128::from(self.clone());
 
-        bytes.bin_serialise_into(stream)
+        bytes.bin_serialise_into(stream, ctx)
     }
 
-    fn bin_deserialise_from<R: Read>(stream: R) -> Result<Self> {
-        let bytes = u128::bin_deserialise_from(stream)?;
+    fn bin_deserialise_from<R: Read>(stream: R, ctx: &SerCtx, limit: &mut DeserialiseLimit) -> Result<Self> {
+        let bytes = u128::bin_deserialise_from(stream, ctx, limit)?;
 
         Ok(Self::from(bytes))
     }
@@ -295,33 +640,37 @@ impl TorSerde for Ipv6Addr {
     fn serialised_length(&self) -> u32 {
         16
     }
+
+    fn min_serialised_length() -> u32 {
+        16
+    }
 }
 
 impl TorSerde for IpAddr {
-    fn bin_serialise_into<W: Write>(&self, mut stream: W) -> Result<u32> {
+    fn bin_serialise_into<W: Write>(&self, mut stream: W, ctx: &SerCtx) -> Result<u32> {
         Ok(match self {
             IpAddr::V4(ipv4) => {
-                4u8.bin_serialise_into(stream.borrow_mut())? + //Address type (4 for ipv4, 6 for ipv6)
-                4u8.bin_serialise_into(stream.borrow_mut())? + //Address length (4 for ipv4, 16 for ipv6)
-                ipv4.bin_serialise_into(stream.borrow_mut())?
+                4u8.bin_serialise_into(stream.borrow_mut(), ctx)? + //Address type (4 for ipv4, 6 for ipv6)
+                4u8.bin_serialise_into(stream.borrow_mut(), ctx)? + //Address length (4 for ipv4, 16 for ipv6)
+                ipv4.bin_serialise_into(stream.borrow_mut(), ctx)?
             }
             IpAddr::V6(ipv6) => {
-                6u8.bin_serialise_into(stream.borrow_mut())? + //Address type (4 for ipv4, 6 for ipv6)
-                16u8.bin_serialise_into(stream.borrow_mut())? + //Address length (4 for ipv4, 16 for ipv6)
-                ipv6.bin_serialise_into(stream.borrow_mut())?
+                6u8.bin_serialise_into(stream.borrow_mut(), ctx)? + //Address type (4 for ipv4, 6 for ipv6)
+                16u8.bin_serialise_into(stream.borrow_mut(), ctx)? + //Address length (4 for ipv4, 16 for ipv6)
+                ipv6.bin_serialise_into(stream.borrow_mut(), ctx)?
             }
         })
     }
 
-    fn bin_deserialise_from<R: Read>(mut stream: R) -> Result<Self> {
-        let atype = u8::bin_deserialise_from(stream.borrow_mut())?;
+    fn bin_deserialise_from<R: Read>(mut stream: R, ctx: &SerCtx, limit: &mut DeserialiseLimit) -> Result<Self> {
+        let atype = u8::bin_deserialise_from(stream.borrow_mut(), ctx, limit)?;
 
-        let _alen = u8::bin_deserialise_from(stream.borrow_mut())?;
+        let _alen = u8::bin_deserialise_from(stream.borrow_mut(), ctx, limit)?;
 
         Ok(match atype {
-            4 => { Self::V4(Ipv4Addr::bin_deserialise_from(stream.borrow_mut())?) }
-            6 => { Self::V6(Ipv6Addr::bin_deserialise_from(stream.borrow_mut())?) }
-            _ => unreachable!()
+            4 => { Self::V4(Ipv4Addr::bin_deserialise_from(stream.borrow_mut(), ctx, limit)?) }
+            6 => { Self::V6(Ipv6Addr::bin_deserialise_from(stream.borrow_mut(), ctx, limit)?) }
+            _ => return Err(ErrorKind::BadDiscriminant(atype as u128))
         })
     }
 
@@ -331,24 +680,267 @@ impl TorSerde for IpAddr {
             IpAddr::V6(ipv6) => {ipv6.serialised_length()}
         }
     }
+
+    fn min_serialised_length() -> u32 {
+        2 + 4
+    }
+}
+
+///An IPv4 address together with a CIDR prefix length, as used in exit-policy and address-range fields
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Ipv4Net {
+    pub addr: Ipv4Addr,
+    pub prefix_len: u8,
+}
+
+impl Ipv4Net {
+    ///Builds a new `Ipv4Net`, rejecting a `prefix_len` wider than 32 bits
+    pub fn new(addr: Ipv4Addr, prefix_len: u8) -> Result<Self> {
+        if prefix_len > 32 {
+            return Err(ErrorKind::InvalidPrefixLength(prefix_len));
+        }
+
+        Ok(Self { addr, prefix_len })
+    }
+
+    ///True if every address covered by `other` is also covered by `self`. When the prefix lengths
+    ///are equal the stored addresses are compared whole, so host bits beyond the prefix must match too
+    pub fn contains(&self, other: &Self) -> bool {
+        if self.prefix_len > other.prefix_len {
+            return false;
+        }
+
+        if self.prefix_len == other.prefix_len {
+            return self.addr == other.addr;
+        }
+
+        if self.prefix_len == 0 {
+            return true;
+        }
+
+        let shift = 32 - self.prefix_len;
+        (u32::from(self.addr) >> shift) == (u32::from(other.addr) >> shift)
+    }
+}
+
+impl std::str::FromStr for Ipv4Net {
+    type Err = ErrorKind;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (addr, prefix_len) = s.split_once('/').ok_or(ErrorKind::InvalidNetFormat)?;
+
+        let addr = addr.parse::<Ipv4Addr>().map_err(|_| ErrorKind::InvalidNetFormat)?;
+        let prefix_len = prefix_len.parse::<u8>().map_err(|_| ErrorKind::InvalidNetFormat)?;
+
+        Self::new(addr, prefix_len)
+    }
+}
+
+impl std::fmt::Display for Ipv4Net {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}/{}", self.addr, self.prefix_len)
+    }
+}
+
+impl TorSerde for Ipv4Net {
+    fn bin_serialise_into<W: Write>(&self, mut stream: W, ctx: &SerCtx) -> Result<u32> {
+        Ok(self.addr.bin_serialise_into(stream.borrow_mut(), ctx)? +
+            self.prefix_len.bin_serialise_into(stream.borrow_mut(), ctx)?)
+    }
+
+    fn bin_deserialise_from<R: Read>(mut stream: R, ctx: &SerCtx, limit: &mut DeserialiseLimit) -> Result<Self> {
+        let addr = Ipv4Addr::bin_deserialise_from(stream.borrow_mut(), ctx, limit)?;
+        let prefix_len = u8::bin_deserialise_from(stream.borrow_mut(), ctx, limit)?;
+
+        Self::new(addr, prefix_len)
+    }
+
+    fn serialised_length(&self) -> u32 {
+        5
+    }
+
+    fn min_serialised_length() -> u32 {
+        5
+    }
+}
+
+///An IPv6 address together with a CIDR prefix length, as used in exit-policy and address-range fields
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Ipv6Net {
+    pub addr: Ipv6Addr,
+    pub prefix_len: u8,
+}
+
+impl Ipv6Net {
+    ///Builds a new `Ipv6Net`, rejecting a `prefix_len` wider than 128 bits
+    pub fn new(addr: Ipv6Addr, prefix_len: u8) -> Result<Self> {
+        if prefix_len > 128 {
+            return Err(ErrorKind::InvalidPrefixLength(prefix_len));
+        }
+
+        Ok(Self { addr, prefix_len })
+    }
+
+    ///True if every address covered by `other` is also covered by `self`. When the prefix lengths
+    ///are equal the stored addresses are compared whole, so host bits beyond the prefix must match too
+    pub fn contains(&self, other: &Self) -> bool {
+        if self.prefix_len > other.prefix_len {
+            return false;
+        }
+
+        if self.prefix_len == other.prefix_len {
+            return self.addr == other.addr;
+        }
+
+        if self.prefix_len == 0 {
+            return true;
+        }
+
+        let shift = 128 - self.prefix_len;
+        (u128::from(self.addr) >> shift) == (u128::from(other.addr) >> shift)
+    }
+}
+
+impl std::str::FromStr for Ipv6Net {
+    type Err = ErrorKind;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (addr, prefix_len) = s.split_once('/').ok_or(ErrorKind::InvalidNetFormat)?;
+
+        let addr = addr.parse::<Ipv6Addr>().map_err(|_| ErrorKind::InvalidNetFormat)?;
+        let prefix_len = prefix_len.parse::<u8>().map_err(|_| ErrorKind::InvalidNetFormat)?;
+
+        Self::new(addr, prefix_len)
+    }
+}
+
+impl std::fmt::Display for Ipv6Net {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}/{}", self.addr, self.prefix_len)
+    }
+}
+
+impl TorSerde for Ipv6Net {
+    fn bin_serialise_into<W: Write>(&self, mut stream: W, ctx: &SerCtx) -> Result<u32> {
+        Ok(self.addr.bin_serialise_into(stream.borrow_mut(), ctx)? +
+            self.prefix_len.bin_serialise_into(stream.borrow_mut(), ctx)?)
+    }
+
+    fn bin_deserialise_from<R: Read>(mut stream: R, ctx: &SerCtx, limit: &mut DeserialiseLimit) -> Result<Self> {
+        let addr = Ipv6Addr::bin_deserialise_from(stream.borrow_mut(), ctx, limit)?;
+        let prefix_len = u8::bin_deserialise_from(stream.borrow_mut(), ctx, limit)?;
+
+        Self::new(addr, prefix_len)
+    }
+
+    fn serialised_length(&self) -> u32 {
+        17
+    }
+
+    fn min_serialised_length() -> u32 {
+        17
+    }
+}
+
+///Either an `Ipv4Net` or an `Ipv6Net`, mirroring how `IpAddr` wraps `Ipv4Addr`/`Ipv6Addr`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IpNet {
+    V4(Ipv4Net),
+    V6(Ipv6Net),
+}
+
+impl IpNet {
+    ///Delegates to `Ipv4Net::contains`/`Ipv6Net::contains` (see there for equal prefix lengths). Always false across address families
+    pub fn contains(&self, other: &Self) -> bool {
+        match (self, other) {
+            (IpNet::V4(a), IpNet::V4(b)) => a.contains(b),
+            (IpNet::V6(a), IpNet::V6(b)) => a.contains(b),
+            _ => false,
+        }
+    }
+}
+
+impl std::str::FromStr for IpNet {
+    type Err = ErrorKind;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (addr, _prefix_len) = s.split_once('/').ok_or(ErrorKind::InvalidNetFormat)?;
+
+        if addr.parse::<Ipv4Addr>().is_ok() {
+            Ok(Self::V4(s.parse()?))
+        } else {
+            Ok(Self::V6(s.parse()?))
+        }
+    }
+}
+
+impl std::fmt::Display for IpNet {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            IpNet::V4(net) => write!(f, "{}", net),
+            IpNet::V6(net) => write!(f, "{}", net),
+        }
+    }
+}
+
+impl TorSerde for IpNet {
+    fn bin_serialise_into<W: Write>(&self, mut stream: W, ctx: &SerCtx) -> Result<u32> {
+        Ok(match self {
+            IpNet::V4(net) => {
+                4u8.bin_serialise_into(stream.borrow_mut(), ctx)? + //Address type (4 for ipv4, 6 for ipv6)
+                4u8.bin_serialise_into(stream.borrow_mut(), ctx)? + //Address length (4 for ipv4, 16 for ipv6)
+                net.bin_serialise_into(stream.borrow_mut(), ctx)?
+            }
+            IpNet::V6(net) => {
+                6u8.bin_serialise_into(stream.borrow_mut(), ctx)? + //Address type (4 for ipv4, 6 for ipv6)
+                16u8.bin_serialise_into(stream.borrow_mut(), ctx)? + //Address length (4 for ipv4, 16 for ipv6)
+                net.bin_serialise_into(stream.borrow_mut(), ctx)?
+            }
+        })
+    }
+
+    fn bin_deserialise_from<R: Read>(mut stream: R, ctx: &SerCtx, limit: &mut DeserialiseLimit) -> Result<Self> {
+        let atype = u8::bin_deserialise_from(stream.borrow_mut(), ctx, limit)?;
+
+        let _alen = u8::bin_deserialise_from(stream.borrow_mut(), ctx, limit)?;
+
+        Ok(match atype {
+            4 => { Self::V4(Ipv4Net::bin_deserialise_from(stream.borrow_mut(), ctx, limit)?) }
+            6 => { Self::V6(Ipv6Net::bin_deserialise_from(stream.borrow_mut(), ctx, limit)?) }
+            _ => return Err(ErrorKind::BadDiscriminant(atype as u128)),
+        })
+    }
+
+    fn serialised_length(&self) -> u32 {
+        2 + match &self {
+            IpNet::V4(net) => {net.serialised_length()}
+            IpNet::V6(net) => {net.serialised_length()}
+        }
+    }
+
+    fn min_serialised_length() -> u32 {
+        2 + 5
+    }
 }
 
 impl TorSerde for String {
-    fn bin_serialise_into<W: Write>(&self, mut stream: W) -> Result<u32> {
+    fn bin_serialise_into<W: Write>(&self, mut stream: W, ctx: &SerCtx) -> Result<u32> {
         //Write the contents of the string to the stream
         stream.borrow_mut().write_all(self.as_bytes())?;
 
         //Append the stream with the null terminator
-        0u8.bin_serialise_into(stream.borrow_mut())?;
+        0u8.bin_serialise_into(stream.borrow_mut(), ctx)?;
 
         Ok(self.serialised_length())
     }
 
-    fn bin_deserialise_from<R: Read>(mut stream: R) -> Result<Self> {
+    fn bin_deserialise_from<R: Read>(mut stream: R, ctx: &SerCtx, limit: &mut DeserialiseLimit) -> Result<Self> {
         let mut string = Vec::new();
 
         loop {
-            let byte = u8::bin_deserialise_from(stream.borrow_mut())?;
+            //The per-byte budget check inside u8's impl is what actually bounds this loop - an
+            //attacker can't grow `string` past whatever the remaining byte budget allows
+            let byte = u8::bin_deserialise_from(stream.borrow_mut(), ctx, limit)?;
 
             if byte == 0 {
                 break;
@@ -365,22 +957,26 @@ impl TorSerde for String {
     fn serialised_length(&self) -> u32 {
         1 + self.len() as u32
     }
+
+    fn min_serialised_length() -> u32 {
+        1
+    }
 }
 
 impl<const N: usize> TorSerde for [u8; N] {
-    fn bin_serialise_into<W: Write>(&self, mut stream: W) -> Result<u32> {
+    fn bin_serialise_into<W: Write>(&self, mut stream: W, ctx: &SerCtx) -> Result<u32> {
         for item in self.iter() {
-            item.bin_serialise_into(stream.borrow_mut())?;
+            item.bin_serialise_into(stream.borrow_mut(), ctx)?;
         }
 
         Ok(N as u32)
     }
 
-    fn bin_deserialise_from<R: Read>(mut stream: R) -> Result<Self> {
+    fn bin_deserialise_from<R: Read>(mut stream: R, ctx: &SerCtx, limit: &mut DeserialiseLimit) -> Result<Self> {
         let mut array = [0u8; N];
 
         for item in array.iter_mut() {
-            *item = u8::bin_deserialise_from(stream.borrow_mut())?;
+            *item = u8::bin_deserialise_from(stream.borrow_mut(), ctx, limit)?;
         }
 
         Ok(array)
@@ -389,4 +985,226 @@ impl<const N: usize> TorSerde for [u8; N] {
     fn serialised_length(&self) -> u32 {
         self.len() as u32
     }
+
+    fn min_serialised_length() -> u32 {
+        N as u32
+    }
+}
+
+///The relay-cell command, recognized, and stream-id header fields, the 4-byte digest, and the
+///variable-length data/padding that together make up the 509-byte body of a RELAY cell
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RelayCell {
+    pub command: u8,
+    pub recognized: u16,
+    pub stream_id: u16,
+    pub digest: u32,
+    pub data: Vec<u8>,
+}
+
+impl RelayCell {
+    ///The fixed size of a relay cell's body on the wire
+    pub const WIRE_LEN: usize = 509;
+
+    ///command(1) + recognized(2) + stream_id(2) + digest(4) + data length(2)
+    const HEADER_LEN: usize = 1 + 2 + 2 + 4 + 2;
+
+    ///The largest `data` this cell can carry once the header and its padding are accounted for
+    pub const DATA_MAX: usize = Self::WIRE_LEN - Self::HEADER_LEN;
+}
+
+impl TorSerde for RelayCell {
+    fn bin_serialise_into<W: Write>(&self, mut stream: W, ctx: &SerCtx) -> Result<u32> {
+        if self.data.len() > Self::DATA_MAX {
+            return Err(ErrorKind::InvalidRelayLength(
+                Self::WIRE_LEN as u32,
+                1,
+                2,
+                2,
+                4,
+                self.data.len() as u32,
+                0,
+            ));
+        }
+
+        let mut total = self.command.bin_serialise_into(stream.borrow_mut(), ctx)?;
+        total += self.recognized.bin_serialise_into(stream.borrow_mut(), ctx)?;
+        total += self.stream_id.bin_serialise_into(stream.borrow_mut(), ctx)?;
+        total += self.digest.bin_serialise_into(stream.borrow_mut(), ctx)?;
+        total += (self.data.len() as u16).bin_serialise_into(stream.borrow_mut(), ctx)?;
+
+        for byte in self.data.iter() {
+            total += byte.bin_serialise_into(stream.borrow_mut(), ctx)?;
+        }
+
+        let padding_len = Self::DATA_MAX - self.data.len();
+        for _ in 0..padding_len {
+            total += 0u8.bin_serialise_into(stream.borrow_mut(), ctx)?;
+        }
+
+        Ok(total)
+    }
+
+    fn bin_deserialise_from<R: Read>(mut stream: R, ctx: &SerCtx, limit: &mut DeserialiseLimit) -> Result<Self> {
+        let command = u8::bin_deserialise_from(stream.borrow_mut(), ctx, limit)?;
+        let recognized = u16::bin_deserialise_from(stream.borrow_mut(), ctx, limit)?;
+        let stream_id = u16::bin_deserialise_from(stream.borrow_mut(), ctx, limit)?;
+        let digest = u32::bin_deserialise_from(stream.borrow_mut(), ctx, limit)?;
+        let data_len = u16::bin_deserialise_from(stream.borrow_mut(), ctx, limit)? as usize;
+
+        if data_len > Self::DATA_MAX {
+            return Err(ErrorKind::InvalidRelayLength(
+                Self::WIRE_LEN as u32,
+                1,
+                2,
+                2,
+                4,
+                data_len as u32,
+                0,
+            ));
+        }
+
+        let mut data = Vec::with_capacity(data_len);
+        for _ in 0..data_len {
+            data.push(u8::bin_deserialise_from(stream.borrow_mut(), ctx, limit)?);
+        }
+
+        let padding_len = Self::DATA_MAX - data_len;
+        for read in 0..padding_len {
+            if u8::bin_deserialise_from(stream.borrow_mut(), ctx, limit).is_err() {
+                return Err(ErrorKind::NotEnoughPadding(padding_len, read));
+            }
+        }
+
+        Ok(Self { command, recognized, stream_id, digest, data })
+    }
+
+    fn serialised_length(&self) -> u32 {
+        Self::WIRE_LEN as u32
+    }
+
+    fn min_serialised_length() -> u32 {
+        Self::WIRE_LEN as u32
+    }
+}
+
+///SHA-1 or SHA3-256 running hash state used to authenticate the sequence of relay cells exchanged
+///on one direction of a circuit. Tor negotiates SHA3-256 relay digests starting at relay protocol
+///version 1; link protocols before that use SHA-1
+#[derive(Clone)]
+enum RunningDigest {
+    Sha1(Sha1),
+    Sha3(Box<Sha3_256>),
+}
+
+impl RunningDigest {
+    ///Picks the hash algorithm negotiated for `version`
+    fn for_version(version: ProtocolVersion) -> Self {
+        if version.0 >= 1 {
+            Self::Sha3(Box::new(Sha3_256::new()))
+        } else {
+            Self::Sha1(Sha1::new())
+        }
+    }
+
+    ///Feeds `data` into the running hash, then returns the first 4 bytes of the digest as it
+    ///stands now, without disturbing the running state used for subsequent cells
+    fn update_and_peek(&mut self, data: &[u8]) -> [u8; 4] {
+        let finalized: Vec<u8> = match self {
+            Self::Sha1(hasher) => {
+                hasher.update(data);
+                hasher.clone().finalize().to_vec()
+            }
+            Self::Sha3(hasher) => {
+                hasher.update(data);
+                hasher.clone().finalize().to_vec()
+            }
+        };
+
+        let mut prefix = [0u8; 4];
+        prefix.copy_from_slice(&finalized[..4]);
+        prefix
+    }
+}
+
+///Wraps a `Write` stream with the rolling digest state for one direction of a circuit, so each
+///relay cell's digest field is computed from - and extends - the same running hash as the last
+pub struct DigestingWriter<W: Write> {
+    inner: W,
+    digest: RunningDigest,
+}
+
+impl<W: Write> DigestingWriter<W> {
+    pub fn new(inner: W, version: ProtocolVersion) -> Self {
+        Self { inner, digest: RunningDigest::for_version(version) }
+    }
+
+    ///Serialises `cell` with its digest field zeroed, feeds the result through the rolling hash,
+    ///writes the real digest back in, and sends the finished 509-byte body to the underlying stream
+    pub fn write_relay_cell(&mut self, cell: &RelayCell, ctx: &SerCtx) -> Result<u32> {
+        let mut to_hash = cell.clone();
+        to_hash.digest = 0;
+        to_hash.recognized = 0;
+
+        let mut body = Vec::with_capacity(RelayCell::WIRE_LEN);
+        to_hash.bin_serialise_into(&mut body, ctx)?;
+
+        let digest_bytes = self.digest.update_and_peek(&body);
+
+        let digest_offset = 1 + 2 + 2; //past command, recognized, stream_id
+        body[digest_offset..digest_offset + 4].copy_from_slice(&digest_bytes);
+
+        self.inner.write_all(&body)?;
+
+        Ok(body.len() as u32)
+    }
+}
+
+///Wraps a `Read` stream with the rolling digest state for one direction of a circuit, verifying
+///each relay cell's digest field against the same running hash the sender used. The hash is
+///rolled forward on every call that reads a full 509-byte body, including ones that return a
+///digest error (a call whose read of the body fails leaves it untouched), so a
+///single corrupted or truncated cell permanently desyncs this reader from the sender and every
+///later cell on the circuit will also fail - by design, since a digest mismatch means the circuit
+///can no longer be trusted and should be torn down rather than resynchronised
+pub struct DigestingReader<R: Read> {
+    inner: R,
+    digest: RunningDigest,
+}
+
+impl<R: Read> DigestingReader<R> {
+    pub fn new(inner: R, version: ProtocolVersion) -> Self {
+        Self { inner, digest: RunningDigest::for_version(version) }
+    }
+
+    ///Reads one 509-byte relay cell body, verifies its digest against the rolling hash, and
+    ///returns the parsed cell. A mismatched digest is only tolerated (as `DiscardedCell`) when the
+    ///cell's `recognized` field also signals it wasn't meant for this hop. The rolling hash still
+    ///advances even when the digest doesn't match (see struct docs), so callers should treat any
+    ///`BadDigest`/`DiscardedCell` error as fatal to the circuit rather than retrying reads on it
+    pub fn read_relay_cell(&mut self, ctx: &SerCtx, limit: &mut DeserialiseLimit) -> Result<RelayCell> {
+        let mut body = [0u8; RelayCell::WIRE_LEN];
+        self.inner.read_exact(&mut body)?;
+
+        let recognized = u16::from_be_bytes([body[1], body[2]]);
+        let stored_digest = u32::from_be_bytes([body[5], body[6], body[7], body[8]]);
+
+        let mut zeroed = body;
+        zeroed[1] = 0; //recognized high byte
+        zeroed[2] = 0; //recognized low byte
+        zeroed[5..9].copy_from_slice(&[0, 0, 0, 0]); //digest
+
+        let predicted_bytes = self.digest.update_and_peek(&zeroed);
+        let predicted = u32::from_be_bytes(predicted_bytes);
+
+        if predicted != stored_digest {
+            return Err(if recognized != 0 {
+                ErrorKind::DiscardedCell(recognized as u128)
+            } else {
+                ErrorKind::BadDigest(predicted, stored_digest)
+            });
+        }
+
+        RelayCell::bin_deserialise_from(body.as_slice(), ctx, limit)
+    }
 }